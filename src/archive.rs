@@ -0,0 +1,107 @@
+use std::io;
+use std::io::prelude::*;
+use std::str;
+
+const MAGIC: &'static [u8] = b"!<arch>\n";
+const HEADER_SIZE: usize = 60;
+
+pub struct Archive {
+    members: Vec<Member>,
+}
+
+pub struct Member {
+    pub name: String,
+    pub object: Box<::Object>,
+}
+
+impl Archive {
+    /// Parses a Unix `ar` archive (as produced for `.a` static libraries),
+    /// handling the GNU `//` long-name table, `/N` name-offset references and
+    /// the leading `/` symbol-index member. Each member is run back through
+    /// [`File::parse`](::elf::file::File::parse) and, if that accepts it,
+    /// exposed behind the crate's [`Object`](::Object) trait; members it
+    /// rejects (COFF objects, for instance) are skipped without an error.
+    pub fn parse<R: io::Read>(r: &mut R) -> Result<Archive, io::Error> {
+        let mut buf = Vec::new();
+        try!(r.read_to_end(&mut buf));
+
+        if buf.len() < MAGIC.len() || &buf[0..MAGIC.len()] != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::Other, "invalid archive magic"));
+        }
+
+        let mut pos = MAGIC.len();
+        let mut longnames: Vec<u8> = Vec::new();
+        let mut members = Vec::new();
+
+        while pos + HEADER_SIZE <= buf.len() {
+            let header = &buf[pos..pos + HEADER_SIZE];
+            if &header[58..60] != b"`\n" {
+                return Err(io::Error::new(io::ErrorKind::Other, "invalid member header"));
+            }
+
+            let raw_name = str::from_utf8(&header[0..16])
+                .unwrap_or("")
+                .trim_end()
+                .to_string();
+            let size: usize = str::from_utf8(&header[48..58])
+                .unwrap_or("")
+                .trim()
+                .parse()
+                .unwrap_or(0);
+
+            pos += HEADER_SIZE;
+            if pos + size > buf.len() {
+                return Err(io::Error::new(io::ErrorKind::Other, "truncated member"));
+            }
+            let data = buf[pos..pos + size].to_vec();
+            pos += size;
+            if size % 2 == 1 {
+                pos += 1; // members are padded to an even offset
+            }
+
+            if raw_name == "//" {
+                longnames = data;
+                continue;
+            }
+            if raw_name == "/" || raw_name == "/SYM64/" {
+                continue; // archive symbol index, not an object
+            }
+
+            let name = if raw_name.starts_with('/') {
+                let off: usize = raw_name[1..].parse().unwrap_or(0);
+                longname(&longnames, off)
+            } else if raw_name.ends_with('/') {
+                raw_name[..raw_name.len() - 1].to_string()
+            } else {
+                raw_name
+            };
+
+            let mut cur = io::Cursor::new(data);
+            if let Ok(file) = ::elf::file::File::parse(&mut cur) {
+                members.push(Member { name: name, object: Box::new(file) });
+            }
+        }
+
+        Ok(Archive { members: members })
+    }
+
+    pub fn members(&self) -> &Vec<Member> {
+        &self.members
+    }
+}
+
+fn longname(table: &[u8], start: usize) -> String {
+    let mut end = table.len();
+    for i in start..table.len() {
+        if table[i] == b'\n' || table[i] == b'/' {
+            end = i;
+            break;
+        }
+    }
+
+    if start >= table.len() {
+        return String::new();
+    }
+
+    String::from_utf8_lossy(&table[start..end]).into_owned()
+}
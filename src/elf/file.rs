@@ -5,10 +5,16 @@ use std::fs;
 use std::fmt;
 use byteorder;
 use byteorder::ReadBytesExt;
+use flate2::read::ZlibDecoder;
 use elf::types;
 use std::collections::HashMap;
 use std::collections::hash_map;
 
+const SHF_COMPRESSED: u64 = 0x800;
+const NT_GNU_BUILD_ID: u32 = 3;
+const ELFCOMPRESS_ZLIB: u32 = 1;
+const ELFCOMPRESS_ZSTD: u32 = 2;
+
 macro_rules! read_u64 {
     ($data:ident, $io:ident) => (
         match $data {
@@ -56,14 +62,95 @@ fn get_elf_string(data: &Vec<u8>, start: usize) -> String {
     ret
 }
 
+fn get_elf_utf8_string(data: &Vec<u8>, start: usize) -> String {
+    if start >= data.len() {
+        return String::new();
+    }
+
+    let mut end = data.len();
+    for i in start..data.len() {
+        if data[i] == 0u8 {
+            end = i;
+            break;
+        }
+    }
+
+    String::from_utf8_lossy(&data[start..end]).into_owned()
+}
+
+fn skip_padding<S: io::Seek>(s: &mut S, size: u32) -> Result<(), io::Error> {
+    let pad = (((size + 3) & !3) - size) as i64;
+    try!(s.seek(io::SeekFrom::Current(pad)));
+    Ok(())
+}
+
+fn elf_hash(name: &str) -> u32 {
+    let mut h: u32 = 0;
+    for c in name.bytes() {
+        h = (h << 4).wrapping_add(c as u32);
+        let g = h & 0xf0000000;
+        h ^= g >> 24;
+        h &= !g;
+    }
+    h
+}
+
+fn gnu_hash(name: &str) -> u32 {
+    let mut h: u32 = 5381;
+    for c in name.bytes() {
+        h = h.wrapping_mul(33).wrapping_add(c as u32);
+    }
+    h
+}
+
 pub struct File {
     hdr: types::FileHeader,
     sections: HashMap<String, Section>,
+    section_names: Vec<String>,
+    segments: Vec<Segment>,
+}
+
+#[derive(Clone)]
+pub struct Symbol {
+    pub name: String,
+    pub value: u64,
+    pub size: u64,
+    pub bind: types::SymbolBind,
+    pub symtype: types::SymbolType,
+    pub vis: types::SymbolVis,
+    pub shndx: u16,
+}
+
+pub struct Note {
+    pub name: String,
+    pub ntype: u32,
+    pub desc: Vec<u8>,
+}
+
+pub struct Relocation {
+    pub offset: u64,
+    pub sym: u32,
+    pub rtype: u32,
+    pub addend: i64,
+    pub symbol: Option<Symbol>,
+}
+
+pub struct Segment {
+    pub ptype: types::ProgType,
+    pub flags: types::ProgFlag,
+    pub offset: u64,
+    pub vaddr: u64,
+    pub paddr: u64,
+    pub filesz: u64,
+    pub memsz: u64,
+    pub align: u64,
 }
 
 pub struct Section {
     hdr: types::SectionHeader,
     data: Vec<u8>,
+    class: types::Class,
+    encoding: types::Data,
 }
 
 impl File {
@@ -182,8 +269,64 @@ impl File {
             sections_lst[i as usize].name = get_elf_string(&sections_data[shstrndx as usize], name_idxs[i as usize] as usize);
         }
 
+        let mut segments = Vec::new();
+        try!(r.seek(io::SeekFrom::Start(phoff)));
+
+        for _ in 0..phnum {
+            let ptype = types::ProgType(try!(read_u32!(data, r)));
+            let flags;
+            let offset;
+            let vaddr;
+            let paddr;
+            let filesz;
+            let memsz;
+            let align;
+
+            match class {
+                types::ELFCLASS32 => {
+                    offset = try!(read_u32!(data, r)) as u64;
+                    vaddr = try!(read_u32!(data, r)) as u64;
+                    paddr = try!(read_u32!(data, r)) as u64;
+                    filesz = try!(read_u32!(data, r)) as u64;
+                    memsz = try!(read_u32!(data, r)) as u64;
+                    flags = types::ProgFlag(try!(read_u32!(data, r)));
+                    align = try!(read_u32!(data, r)) as u64;
+                }
+                types::ELFCLASS64 => {
+                    flags = types::ProgFlag(try!(read_u32!(data, r)));
+                    offset = try!(read_u64!(data, r));
+                    vaddr = try!(read_u64!(data, r));
+                    paddr = try!(read_u64!(data, r));
+                    filesz = try!(read_u64!(data, r));
+                    memsz = try!(read_u64!(data, r));
+                    align = try!(read_u64!(data, r));
+                }
+                _ => unreachable!(),
+            }
+
+            segments.push(Segment {
+                ptype: ptype,
+                flags: flags,
+                offset: offset,
+                vaddr: vaddr,
+                paddr: paddr,
+                filesz: filesz,
+                memsz: memsz,
+                align: align,
+            });
+        }
+
+        let section_names: Vec<String> = sections_lst.iter().map(|s| s.name.clone()).collect();
+
+        let encoding = data;
+
         for (hdr, data) in sections_lst.into_iter().zip(sections_data.into_iter()) {
-            sections.insert(hdr.name.clone(), Section { hdr: hdr, data: data });
+            sections.insert(hdr.name.clone(), Section {
+                hdr: hdr,
+                data: data,
+                class: class,
+                encoding: encoding,
+            });
         }
 
         Ok(File {
@@ -198,12 +341,372 @@ impl File {
                 entrypoint: entry,
             },
             sections: sections,
+            section_names: section_names,
+            segments: segments,
         })
     }
 
     pub fn sections(&self) -> &HashMap<String, Section> {
         &self.sections
     }
+
+    /// Returns the symbols contained in the `.symtab` section, or an empty
+    /// vector if the file has been stripped of its static symbol table.
+    pub fn symbols(&self) -> Result<Vec<Symbol>, io::Error> {
+        self.parse_symbols(".symtab")
+    }
+
+    /// Returns the dynamic symbols contained in the `.dynsym` section.
+    pub fn dynamic_symbols(&self) -> Result<Vec<Symbol>, io::Error> {
+        self.parse_symbols(".dynsym")
+    }
+
+    /// Returns the ELF class (`ELFCLASS32` / `ELFCLASS64`), i.e. the bitness
+    /// of the object.
+    pub fn class(&self) -> types::Class {
+        self.hdr.class
+    }
+
+    /// Returns the byte order (`ELFDATA2LSB` / `ELFDATA2MSB`) the object is
+    /// encoded in.
+    pub fn endianness(&self) -> types::Data {
+        self.hdr.data
+    }
+
+    pub fn segments(&self) -> &Vec<Segment> {
+        &self.segments
+    }
+
+    /// Returns the loadable segment whose virtual address range contains
+    /// `addr`, if any.
+    pub fn segment_for_address(&self, addr: u64) -> Option<&Segment> {
+        self.segments.iter().find(|s| {
+            s.ptype == types::PT_LOAD && addr >= s.vaddr && addr < s.vaddr + s.memsz
+        })
+    }
+
+    /// Returns the section whose virtual address range contains `addr`, if
+    /// any. Only sections flagged `SHF_ALLOC` have a meaningful address.
+    pub fn section_for_address(&self, addr: u64) -> Option<&Section> {
+        self.sections.values().find(|s| {
+            s.hdr.addr != 0 && addr >= s.hdr.addr && addr < s.hdr.addr + s.hdr.size
+        })
+    }
+
+    /// Decodes the relocation entries of the named `SHT_REL` or `SHT_RELA`
+    /// section, resolving each entry's referenced symbol through the
+    /// section's linked symbol table.
+    pub fn relocations(&self, name: &str) -> Result<Vec<Relocation>, io::Error> {
+        let sect = match self.sections.get(name) {
+            Some(sect) => sect,
+            None => return Ok(Vec::new()),
+        };
+
+        let rela = sect.hdr.shtype == types::SHT_RELA;
+
+        let syms = match self.section_names.get(sect.hdr.link as usize) {
+            Some(n) => try!(self.parse_symbols(n)),
+            None => Vec::new(),
+        };
+
+        let data = self.hdr.data;
+        let entsize = match (self.hdr.class, rela) {
+            (types::ELFCLASS32, false) => 8,
+            (types::ELFCLASS32, true) => 12,
+            (types::ELFCLASS64, false) => 16,
+            (types::ELFCLASS64, true) => 24,
+            _ => return Err(io::Error::new(io::ErrorKind::Other, "invalid class")),
+        };
+
+        let mut cur = io::Cursor::new(&sect.data);
+        let mut relocs = Vec::with_capacity(sect.data.len() / entsize);
+
+        for _ in 0..(sect.data.len() / entsize) {
+            let offset;
+            let sym;
+            let rtype;
+            let addend;
+
+            match self.hdr.class {
+                types::ELFCLASS32 => {
+                    offset = try!(read_u32!(data, cur)) as u64;
+                    let info = try!(read_u32!(data, cur));
+                    sym = info >> 8;
+                    rtype = info & 0xff;
+                    addend = if rela { try!(read_u32!(data, cur)) as i32 as i64 } else { 0 };
+                }
+                types::ELFCLASS64 => {
+                    offset = try!(read_u64!(data, cur));
+                    let info = try!(read_u64!(data, cur));
+                    sym = (info >> 32) as u32;
+                    rtype = (info & 0xffffffff) as u32;
+                    addend = if rela { try!(read_u64!(data, cur)) as i64 } else { 0 };
+                }
+                _ => unreachable!(),
+            }
+
+            relocs.push(Relocation {
+                offset: offset,
+                sym: sym,
+                rtype: rtype,
+                addend: addend,
+                symbol: syms.get(sym as usize).cloned(),
+            });
+        }
+
+        Ok(relocs)
+    }
+
+    /// Walks every `SHT_NOTE` section and returns the contained notes, each
+    /// decoded as `n_namesz`/`n_descsz`/`n_type` followed by the 4-byte
+    /// aligned name and descriptor.
+    pub fn notes(&self) -> Result<Vec<Note>, io::Error> {
+        let data = self.hdr.data;
+        let mut notes = Vec::new();
+
+        for sect in self.sections.values() {
+            if sect.hdr.shtype != types::SHT_NOTE {
+                continue;
+            }
+
+            let mut cur = io::Cursor::new(&sect.data);
+            loop {
+                let namesz = match read_u32!(data, cur) {
+                    Ok(v) => v,
+                    Err(_) => break,
+                };
+                let descsz = try!(read_u32!(data, cur));
+                let ntype = try!(read_u32!(data, cur));
+
+                let mut namebuf = vec![0u8; namesz as usize];
+                try!(cur.read_exact(&mut namebuf));
+                if namebuf.last() == Some(&0) {
+                    namebuf.pop();
+                }
+                let name = String::from_utf8_lossy(&namebuf).into_owned();
+                try!(skip_padding(&mut cur, namesz));
+
+                let mut desc = vec![0u8; descsz as usize];
+                try!(cur.read_exact(&mut desc));
+                try!(skip_padding(&mut cur, descsz));
+
+                notes.push(Note { name: name, ntype: ntype, desc: desc });
+            }
+        }
+
+        Ok(notes)
+    }
+
+    /// Returns the descriptor of the `NT_GNU_BUILD_ID` note, the identifier
+    /// symbol servers and core-dump matchers key on, if the object carries
+    /// one.
+    pub fn build_id(&self) -> Result<Option<Vec<u8>>, io::Error> {
+        let notes = try!(self.notes());
+        for note in notes {
+            if note.ntype == NT_GNU_BUILD_ID && note.name == "GNU" {
+                return Ok(Some(note.desc));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Resolves a dynamic symbol by name, using the `.gnu.hash` table when
+    /// present and falling back to the SysV `.hash` table, or finally to a
+    /// linear scan of `.dynsym` when the object carries no hash section.
+    pub fn lookup_symbol(&self, name: &str) -> Result<Option<Symbol>, io::Error> {
+        if self.sections.contains_key(".gnu.hash") {
+            self.lookup_gnu_hash(name)
+        } else if self.sections.contains_key(".hash") {
+            self.lookup_sysv_hash(name)
+        } else {
+            let syms = try!(self.dynamic_symbols());
+            Ok(syms.into_iter().find(|s| s.name == name))
+        }
+    }
+
+    fn lookup_sysv_hash(&self, name: &str) -> Result<Option<Symbol>, io::Error> {
+        let sect = match self.sections.get(".hash") {
+            Some(sect) => sect,
+            None => return Ok(None),
+        };
+        let syms = try!(self.dynamic_symbols());
+
+        let data = self.hdr.data;
+        let mut cur = io::Cursor::new(&sect.data);
+        let nbucket = try!(read_u32!(data, cur));
+        let nchain = try!(read_u32!(data, cur));
+
+        let mut buckets = Vec::with_capacity(nbucket as usize);
+        for _ in 0..nbucket {
+            buckets.push(try!(read_u32!(data, cur)));
+        }
+        let mut chains = Vec::with_capacity(nchain as usize);
+        for _ in 0..nchain {
+            chains.push(try!(read_u32!(data, cur)));
+        }
+
+        if nbucket == 0 {
+            return Ok(None);
+        }
+
+        let hash = elf_hash(name);
+        let mut idx = buckets[(hash % nbucket) as usize];
+        while idx != 0 {
+            match syms.get(idx as usize) {
+                Some(sym) if sym.name == name => return Ok(Some(sym.clone())),
+                _ => {}
+            }
+            match chains.get(idx as usize) {
+                Some(&next) => idx = next,
+                None => break,
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn lookup_gnu_hash(&self, name: &str) -> Result<Option<Symbol>, io::Error> {
+        let sect = match self.sections.get(".gnu.hash") {
+            Some(sect) => sect,
+            None => return Ok(None),
+        };
+        let syms = try!(self.dynamic_symbols());
+
+        let data = self.hdr.data;
+        let bitcount: u32 = match self.hdr.class {
+            types::ELFCLASS32 => 32,
+            types::ELFCLASS64 => 64,
+            _ => return Err(io::Error::new(io::ErrorKind::Other, "invalid class")),
+        };
+
+        let mut cur = io::Cursor::new(&sect.data);
+        let nbuckets = try!(read_u32!(data, cur));
+        let symoffset = try!(read_u32!(data, cur));
+        let bloom_size = try!(read_u32!(data, cur));
+        let bloom_shift = try!(read_u32!(data, cur));
+
+        let mut bloom = Vec::with_capacity(bloom_size as usize);
+        for _ in 0..bloom_size {
+            let word = match self.hdr.class {
+                types::ELFCLASS32 => try!(read_u32!(data, cur)) as u64,
+                types::ELFCLASS64 => try!(read_u64!(data, cur)),
+                _ => unreachable!(),
+            };
+            bloom.push(word);
+        }
+
+        let mut buckets = Vec::with_capacity(nbuckets as usize);
+        for _ in 0..nbuckets {
+            buckets.push(try!(read_u32!(data, cur)));
+        }
+
+        let mut chains = Vec::new();
+        while let Ok(word) = read_u32!(data, cur) {
+            chains.push(word);
+        }
+
+        if nbuckets == 0 || bloom_size == 0 {
+            return Ok(None);
+        }
+
+        let hash = gnu_hash(name);
+        let mask = (1u64 << (hash % bitcount)) | (1u64 << ((hash >> bloom_shift) % bitcount));
+        let word = bloom[((hash / bitcount) % bloom_size) as usize];
+        if word & mask != mask {
+            return Ok(None);
+        }
+
+        let mut idx = buckets[(hash % nbuckets) as usize];
+        if idx < symoffset {
+            return Ok(None);
+        }
+
+        loop {
+            let chain_idx = (idx - symoffset) as usize;
+            let chash = match chains.get(chain_idx) {
+                Some(&c) => c,
+                None => break,
+            };
+            if (chash | 1) == (hash | 1) {
+                match syms.get(idx as usize) {
+                    Some(sym) if sym.name == name => return Ok(Some(sym.clone())),
+                    _ => {}
+                }
+            }
+            if chash & 1 != 0 {
+                break;
+            }
+            idx += 1;
+        }
+
+        Ok(None)
+    }
+
+    fn parse_symbols(&self, name: &str) -> Result<Vec<Symbol>, io::Error> {
+        let sect = match self.sections.get(name) {
+            Some(sect) => sect,
+            None => return Ok(Vec::new()),
+        };
+
+        let strtab = self.section_names.get(sect.hdr.link as usize)
+            .and_then(|n| self.sections.get(n));
+
+        let data = self.hdr.data;
+        let entsize = match self.hdr.class {
+            types::ELFCLASS32 => 16,
+            types::ELFCLASS64 => 24,
+            _ => return Err(io::Error::new(io::ErrorKind::Other, "invalid class")),
+        };
+
+        let mut cur = io::Cursor::new(&sect.data);
+        let mut syms = Vec::with_capacity(sect.data.len() / entsize);
+
+        for _ in 0..(sect.data.len() / entsize) {
+            let name_off;
+            let value;
+            let size;
+            let info;
+            let other;
+            let shndx;
+
+            match self.hdr.class {
+                types::ELFCLASS32 => {
+                    name_off = try!(read_u32!(data, cur));
+                    value = try!(read_u32!(data, cur)) as u64;
+                    size = try!(read_u32!(data, cur)) as u64;
+                    info = try!(cur.read_u8());
+                    other = try!(cur.read_u8());
+                    shndx = try!(read_u16!(data, cur));
+                }
+                types::ELFCLASS64 => {
+                    name_off = try!(read_u32!(data, cur));
+                    info = try!(cur.read_u8());
+                    other = try!(cur.read_u8());
+                    shndx = try!(read_u16!(data, cur));
+                    value = try!(read_u64!(data, cur));
+                    size = try!(read_u64!(data, cur));
+                }
+                _ => unreachable!(),
+            }
+
+            let name = match strtab {
+                Some(s) => get_elf_utf8_string(&s.data, name_off as usize),
+                None => String::new(),
+            };
+
+            syms.push(Symbol {
+                name: name,
+                value: value,
+                size: size,
+                bind: types::SymbolBind(info >> 4),
+                symtype: types::SymbolType(info & 0xf),
+                vis: types::SymbolVis(other & 0x3),
+                shndx: shndx,
+            });
+        }
+
+        Ok(syms)
+    }
 }
 
 impl fmt::Display for File {
@@ -219,16 +722,31 @@ impl fmt::Display for File {
 }
 
 impl ::Object for File {
+    // NOTE: the `Object` trait only carries `arch()` and `get_section()`, so bitness and
+    // endianness are not reachable through `&Object`. They are exposed as the
+    // inherent `File::class()` / `File::endianness()` accessors instead;
+    // surfacing them generically would require widening the crate-root trait.
     fn arch(&self) -> ::Arch {
-        ::Arch::Unknown
+        match self.hdr.machine {
+            types::EM_386 => ::Arch::X86,
+            types::EM_X86_64 => ::Arch::X86_64,
+            types::EM_ARM => ::Arch::ARM,
+            _ => ::Arch::Unknown,
+        }
     }
     fn get_section(&self, name: &str) -> Option<::Section> {
         if let Some(sect) = self.sections.get(name) {
+            // Skip the section rather than hand back still-compressed bytes
+            // dressed up as ordinary contents when decompression fails.
+            let data = match sect.decompressed_data() {
+                Ok(data) => data,
+                Err(_) => return None,
+            };
             Some(::Section {
                 name: sect.hdr.name.clone(),
                 addr: sect.hdr.addr,
-                size: sect.hdr.size,
-                data: sect.data.clone(), // FIXME don't clone data, store sections
+                size: data.len() as u64, // matches the (possibly inflated) data
+                data: data, // FIXME don't clone data, store sections
             })
         } else {
             None
@@ -243,6 +761,60 @@ impl Section {
     pub fn data(&self) -> &Vec<u8> {
         &self.data
     }
+
+    /// Returns the section's contents, transparently inflating them when the
+    /// section is compressed either via the `SHF_COMPRESSED` flag (with an
+    /// `Elf(32|64)_Chdr` header) or the older GNU `.zdebug_*` convention
+    /// (`"ZLIB"` magic followed by a big-endian uncompressed size). Only zlib
+    /// is inflated: a section whose `ch_type` is `ELFCOMPRESS_ZSTD` yields an
+    /// error. For an uncompressed section this is just a copy of
+    /// [`data`](Section::data).
+    pub fn decompressed_data(&self) -> Result<Vec<u8>, io::Error> {
+        if self.hdr.flags.0 & SHF_COMPRESSED != 0 {
+            let data = self.encoding;
+            let mut cur = io::Cursor::new(&self.data);
+            let ch_type;
+            let hdrlen;
+
+            match self.class {
+                types::ELFCLASS32 => {
+                    ch_type = try!(read_u32!(data, cur));
+                    let _ch_size = try!(read_u32!(data, cur));
+                    let _ch_addralign = try!(read_u32!(data, cur));
+                    hdrlen = 12;
+                }
+                types::ELFCLASS64 => {
+                    ch_type = try!(read_u32!(data, cur));
+                    let _ch_reserved = try!(read_u32!(data, cur));
+                    let _ch_size = try!(read_u64!(data, cur));
+                    let _ch_addralign = try!(read_u64!(data, cur));
+                    hdrlen = 24;
+                }
+                _ => return Err(io::Error::new(io::ErrorKind::Other, "invalid class")),
+            }
+
+            match ch_type {
+                ELFCOMPRESS_ZLIB => inflate_zlib(&self.data[hdrlen..]),
+                ELFCOMPRESS_ZSTD => Err(io::Error::new(io::ErrorKind::Other,
+                    "zstd-compressed sections are not supported")),
+                _ => Err(io::Error::new(io::ErrorKind::Other, "unknown compression type")),
+            }
+        } else if self.hdr.name.starts_with(".zdebug") {
+            if self.data.len() < 12 || &self.data[0..4] != b"ZLIB" {
+                return Err(io::Error::new(io::ErrorKind::Other, "invalid zdebug header"));
+            }
+            inflate_zlib(&self.data[12..])
+        } else {
+            Ok(self.data.clone())
+        }
+    }
+}
+
+fn inflate_zlib(data: &[u8]) -> Result<Vec<u8>, io::Error> {
+    let mut out = Vec::new();
+    let mut dec = ZlibDecoder::new(data);
+    try!(dec.read_to_end(&mut out));
+    Ok(out)
 }
 
 impl fmt::Display for Section {